@@ -0,0 +1,198 @@
+//! Color and text-style configuration.
+//!
+//! A component's `color` may be written three ways in `statusline.toml`:
+//!
+//! * an integer palette index (`color = 208`) → [`Color::Fixed`];
+//! * a hex string (`color = "#ff8800"`) → [`Color::RGB`];
+//! * a named color (`color = "green"`, `color = "bright_yellow"`).
+//!
+//! An optional `style` list (`style = ["bold", "underline"]`) layers text
+//! attributes on top via [`ansi_term::Style`].
+
+use std::fmt;
+
+use ansi_term::{Color, Style};
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+
+/// A color parsed from config, before it is turned into an [`ansi_term::Color`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorSpec {
+    Fixed(u8),
+    Rgb(u8, u8, u8),
+    Named(Color),
+}
+
+impl ColorSpec {
+    pub fn color(&self) -> Color {
+        match self {
+            ColorSpec::Fixed(n) => Color::Fixed(*n),
+            ColorSpec::Rgb(r, g, b) => Color::RGB(*r, *g, *b),
+            ColorSpec::Named(color) => *color,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ColorSpecVisitor)
+    }
+}
+
+struct ColorSpecVisitor;
+
+impl<'de> Visitor<'de> for ColorSpecVisitor {
+    type Value = ColorSpec;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a palette index, a hex string like \"#ff8800\", or a color name")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        fixed_from(value as i64)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        fixed_from(value)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        // Environment variables always arrive as strings, so a bare number is
+        // still a palette index here.
+        if let Ok(index) = value.parse::<i64>() {
+            return fixed_from(index);
+        }
+
+        if let Some(hex) = value.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(|| E::custom(format!("invalid hex color `{value}`")));
+        }
+
+        named(value).ok_or_else(|| E::custom(format!("unknown color name `{value}`")))
+    }
+}
+
+fn fixed_from<E: de::Error>(value: i64) -> Result<ColorSpec, E> {
+    u8::try_from(value)
+        .map(ColorSpec::Fixed)
+        .map_err(|_| E::custom(format!("palette index `{value}` out of range (0-255)")))
+}
+
+fn parse_hex(hex: &str) -> Option<ColorSpec> {
+    if !hex.is_ascii() || hex.len() != 6 {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+    Some(ColorSpec::Rgb(r, g, b))
+}
+
+fn named(name: &str) -> Option<ColorSpec> {
+    let color = match name {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "purple" | "magenta" => Color::Purple,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        // The bright variants map onto the 8-15 range of the 256-color palette.
+        "bright_black" => Color::Fixed(8),
+        "bright_red" => Color::Fixed(9),
+        "bright_green" => Color::Fixed(10),
+        "bright_yellow" => Color::Fixed(11),
+        "bright_blue" => Color::Fixed(12),
+        "bright_purple" | "bright_magenta" => Color::Fixed(13),
+        "bright_cyan" => Color::Fixed(14),
+        "bright_white" => Color::Fixed(15),
+        _ => return None,
+    };
+    Some(ColorSpec::Named(color))
+}
+
+/// A single text attribute layered on top of a color.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StyleAttr {
+    Bold,
+    Dimmed,
+    Italic,
+    Underline,
+    Blink,
+    Reverse,
+    Hidden,
+    Strikethrough,
+}
+
+/// Layer `attrs` onto an existing [`Style`].
+pub fn apply(style: Style, attrs: &[StyleAttr]) -> Style {
+    attrs.iter().fold(style, |style, attr| match attr {
+        StyleAttr::Bold => style.bold(),
+        StyleAttr::Dimmed => style.dimmed(),
+        StyleAttr::Italic => style.italic(),
+        StyleAttr::Underline => style.underline(),
+        StyleAttr::Blink => style.blink(),
+        StyleAttr::Reverse => style.reverse(),
+        StyleAttr::Hidden => style.hidden(),
+        StyleAttr::Strikethrough => style.strikethrough(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_palette_index() {
+        assert_eq!(ColorSpec::Fixed(208), serde_json::from_str("208").unwrap());
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(
+            ColorSpec::Rgb(0xff, 0x88, 0x00),
+            serde_json::from_str("\"#ff8800\"").unwrap(),
+        );
+    }
+
+    #[test]
+    fn rejects_non_ascii_hex_without_panicking() {
+        // Six bytes but a multibyte char straddling a slice boundary must not
+        // panic on a non-char-boundary index; it's simply an invalid color.
+        assert!(serde_json::from_str::<ColorSpec>("\"#aébcd\"").is_err());
+    }
+
+    #[test]
+    fn parses_named_color() {
+        assert_eq!(
+            ColorSpec::Named(Color::Green),
+            serde_json::from_str("\"green\"").unwrap(),
+        );
+    }
+
+    #[test]
+    fn parses_numeric_string_as_index() {
+        assert_eq!(ColorSpec::Fixed(200), serde_json::from_str("\"200\"").unwrap());
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        assert!(serde_json::from_str::<ColorSpec>("999").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_name() {
+        assert!(serde_json::from_str::<ColorSpec>("\"chartreuse\"").is_err());
+    }
+
+    #[test]
+    fn apply_layers_attributes() {
+        let style = apply(Color::Red.normal(), &[StyleAttr::Bold, StyleAttr::Underline]);
+        assert!(style.is_bold);
+        assert!(style.is_underline);
+    }
+}
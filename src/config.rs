@@ -1,33 +1,201 @@
 use std::env;
 use std::sync;
 
+use ansi_term::{Color, Style};
 use serde::Deserialize;
 
+use crate::color::{self, ColorSpec, StyleAttr};
+
 pub static CONFIG: sync::OnceLock<ClaudeStatusLineConfig> = sync::OnceLock::new();
 
+/// The components in the order they appear in the default statusline.
+pub const DEFAULT_ORDER: [&str; 5] = ["model", "percentage", "tokens", "cost", "duration"];
+
+/// Every component name that may be referenced from `format`/`order` config.
+/// The default layout is [`DEFAULT_ORDER`]; the session components are opt-in.
+pub const COMPONENTS: [&str; 9] = [
+    "model",
+    "percentage",
+    "tokens",
+    "cost",
+    "duration",
+    "session_cost",
+    "session_tokens",
+    "latency_mean",
+    "latency_p95",
+];
+
 #[derive(Deserialize, Default, Clone, Debug)]
 pub struct ClaudeStatusLineConfig {
+    pub format: Option<String>,
+    pub order: Option<Vec<String>>,
+    pub session: Option<ClaudeStatusLineSessionConfig>,
     pub cost: Option<ClaudeStatusLineComponentConfig>,
     pub duration: Option<ClaudeStatusLineComponentConfig>,
     pub model: Option<ClaudeStatusLineComponentConfig>,
     pub percentage: Option<ClaudeStatusLineComponentConfig>,
     pub tokens: Option<ClaudeStatusLineComponentConfig>,
+    pub session_cost: Option<ClaudeStatusLineComponentConfig>,
+    pub session_tokens: Option<ClaudeStatusLineComponentConfig>,
+    pub latency_mean: Option<ClaudeStatusLineComponentConfig>,
+    pub latency_p95: Option<ClaudeStatusLineComponentConfig>,
+}
+
+impl ClaudeStatusLineConfig {
+    /// The per-component config for `name`, if one is recognized.
+    pub fn component(&self, name: &str) -> Option<&ClaudeStatusLineComponentConfig> {
+        match name {
+            "cost" => self.cost.as_ref(),
+            "duration" => self.duration.as_ref(),
+            "model" => self.model.as_ref(),
+            "percentage" => self.percentage.as_ref(),
+            "tokens" => self.tokens.as_ref(),
+            "session_cost" => self.session_cost.as_ref(),
+            "session_tokens" => self.session_tokens.as_ref(),
+            "latency_mean" => self.latency_mean.as_ref(),
+            "latency_p95" => self.latency_p95.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The component names to render, honoring `order` and dropping any whose
+    /// component config is `disabled`.
+    pub fn components(&self) -> Vec<&str> {
+        let order: Vec<&str> = match &self.order {
+            Some(order) => order.iter().map(String::as_str).collect(),
+            None => DEFAULT_ORDER.to_vec(),
+        };
+
+        order
+            .into_iter()
+            .filter(|name| {
+                if COMPONENTS.contains(name) {
+                    true
+                } else {
+                    eprintln!("claude-statusline: ignoring unknown component `{name}` in `order`");
+                    false
+                }
+            })
+            .filter(|name| !self.component(name).is_some_and(|c| c.is_disabled()))
+            .collect()
+    }
+}
+
+/// Configuration for the cross-invocation session tracking subsystem.
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct ClaudeStatusLineSessionConfig {
+    enabled: Option<bool>,
+    path: Option<String>,
+    history_limit: Option<usize>,
+    max_sessions: Option<usize>,
 }
 
+impl ClaudeStatusLineSessionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+
+    /// The history file path, defaulting to `~/.claude/statusline-history.jsonl`.
+    pub fn path(&self) -> String {
+        self.path.clone().unwrap_or_else(|| {
+            let home = env::var("HOME").unwrap_or_default();
+            format!("{home}/.claude/statusline-history.jsonl")
+        })
+    }
+
+    pub fn history_limit(&self) -> usize {
+        self.history_limit.unwrap_or(DEFAULT_HISTORY_LIMIT)
+    }
+
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions.unwrap_or(DEFAULT_MAX_SESSIONS)
+    }
+}
+
+/// The default per-session cap on stored history lines. Kept small: the file is
+/// re-read and fully rewritten on every invocation, so it must stay tiny.
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// The default cap on the number of distinct sessions retained, so the file
+/// stays bounded no matter how many sessions accumulate over a user's lifetime.
+const DEFAULT_MAX_SESSIONS: usize = 10;
+
 #[derive(Deserialize, Default, Clone, Debug)]
 pub struct ClaudeStatusLineComponentConfig {
-    color: Option<u8>,
+    color: Option<ColorSpec>,
     icon: Option<String>,
+    disabled: Option<bool>,
+    style: Option<Vec<StyleAttr>>,
+    // Percentage-only knobs for theming the context bar.
+    threshold_high: Option<i32>,
+    threshold_medium: Option<i32>,
+    color_high: Option<ColorSpec>,
+    color_medium: Option<ColorSpec>,
+    color_low: Option<ColorSpec>,
 }
 
 impl ClaudeStatusLineComponentConfig {
-    pub fn get_color_or(&self, default: u8) -> u8 {
-        self.color.unwrap_or(default)
+    /// The configured color, or `default` when none is set.
+    pub fn get_color_or(&self, default: Color) -> Color {
+        self.color.as_ref().map(ColorSpec::color).unwrap_or(default)
+    }
+
+    /// A [`Style`] painting with the configured (or default) color and any
+    /// configured text attributes.
+    pub fn get_style_or(&self, default: Color) -> Style {
+        self.style_with(self.get_color_or(default))
+    }
+
+    /// A [`Style`] painting with an already-chosen `color`, layering any
+    /// configured text attributes on top. Used when the color is decided
+    /// elsewhere, as with the percentage thresholds.
+    pub fn style_with(&self, color: Color) -> Style {
+        match &self.style {
+            Some(attrs) => color::apply(color.normal(), attrs),
+            None => color.normal(),
+        }
     }
 
     pub fn get_icon_or<'a>(&'a self, default: &'a str) -> &'a str {
         self.icon.as_deref().unwrap_or(default)
     }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.unwrap_or(false)
+    }
+
+    /// Whether no field was configured, so the component can be dropped to `None`.
+    fn is_empty(&self) -> bool {
+        self.color.is_none()
+            && self.icon.is_none()
+            && self.disabled.is_none()
+            && self.style.is_none()
+            && self.threshold_high.is_none()
+            && self.threshold_medium.is_none()
+            && self.color_high.is_none()
+            && self.color_medium.is_none()
+            && self.color_low.is_none()
+    }
+
+    pub fn threshold_high_or(&self, default: i32) -> i32 {
+        self.threshold_high.unwrap_or(default)
+    }
+
+    pub fn threshold_medium_or(&self, default: i32) -> i32 {
+        self.threshold_medium.unwrap_or(default)
+    }
+
+    pub fn color_high_or(&self, default: Color) -> Color {
+        self.color_high.as_ref().map(ColorSpec::color).unwrap_or(default)
+    }
+
+    pub fn color_medium_or(&self, default: Color) -> Color {
+        self.color_medium.as_ref().map(ColorSpec::color).unwrap_or(default)
+    }
+
+    pub fn color_low_or(&self, default: Color) -> Color {
+        self.color_low.as_ref().map(ColorSpec::color).unwrap_or(default)
+    }
 }
 
 pub fn get_config() -> &'static ClaudeStatusLineConfig {
@@ -47,15 +215,108 @@ fn load_config() -> ClaudeStatusLineConfig {
 }
 
 fn load_config_from(config_path: &str) -> ClaudeStatusLineConfig {
-    let settings = ::config::Config::builder()
+    let settings = match ::config::Config::builder()
         .add_source(::config::File::with_name(config_path).required(false))
         .add_source(::config::Environment::with_prefix("CLAUDE_STATUSLINE").separator("_"))
         .build()
-        .unwrap();
+    {
+        Ok(settings) => settings,
+        // A malformed file (or otherwise unbuildable source) shouldn't take the
+        // whole statusline down; fall back to defaults and say why.
+        Err(err) => {
+            eprintln!("claude-statusline: ignoring unreadable config: {err}");
+            return ClaudeStatusLineConfig::default();
+        }
+    };
+
+    ClaudeStatusLineConfig {
+        format: get_field(&settings, "format"),
+        order: get_field(&settings, "order"),
+        session: load_session(&settings),
+        cost: load_component(&settings, "cost"),
+        duration: load_component(&settings, "duration"),
+        model: load_component(&settings, "model"),
+        percentage: load_component(&settings, "percentage"),
+        tokens: load_component(&settings, "tokens"),
+        session_cost: load_component(&settings, "session_cost"),
+        session_tokens: load_component(&settings, "session_tokens"),
+        latency_mean: load_component(&settings, "latency_mean"),
+        latency_p95: load_component(&settings, "latency_p95"),
+    }
+}
+
+/// Deserialize a single config value, recovering field-by-field: a missing key
+/// yields `None` silently, while a present-but-invalid value is dropped with a
+/// warning so valid sibling fields are still honored.
+fn get_field<T: for<'de> Deserialize<'de>>(
+    settings: &::config::Config,
+    key: &str,
+) -> Option<T> {
+    match settings.get::<T>(key) {
+        Ok(value) => Some(value),
+        Err(::config::ConfigError::NotFound(_)) => None,
+        Err(err) => {
+            eprintln!("claude-statusline: ignoring invalid `{key}`: {err}");
+            None
+        }
+    }
+}
+
+/// Assemble the session config from its individually-recovered fields, so a
+/// single bad value (e.g. a non-numeric `history_limit`) is dropped with a
+/// warning rather than discarding the whole `[session]` block. Returns `None`
+/// when the section is entirely absent.
+fn load_session(settings: &::config::Config) -> Option<ClaudeStatusLineSessionConfig> {
+    let enabled = get_field(settings, "session.enabled");
+    let path = get_field(settings, "session.path");
+    let history_limit = get_field(settings, "session.history_limit");
+    let max_sessions = get_field(settings, "session.max_sessions");
 
-    settings
-        .try_deserialize::<ClaudeStatusLineConfig>()
-        .unwrap()
+    if enabled.is_none() && path.is_none() && history_limit.is_none() && max_sessions.is_none() {
+        None
+    } else {
+        Some(ClaudeStatusLineSessionConfig {
+            enabled,
+            path,
+            history_limit,
+            max_sessions,
+        })
+    }
+}
+
+/// Assemble a component's config from its individually-recovered fields,
+/// returning `None` when the component is entirely absent.
+fn load_component(
+    settings: &::config::Config,
+    name: &str,
+) -> Option<ClaudeStatusLineComponentConfig> {
+    let color = get_field(settings, &format!("{name}.color"));
+    let icon = get_field(settings, &format!("{name}.icon"));
+    let disabled = get_field(settings, &format!("{name}.disabled"));
+    let style = get_field(settings, &format!("{name}.style"));
+    let threshold_high = get_field(settings, &format!("{name}.threshold_high"));
+    let threshold_medium = get_field(settings, &format!("{name}.threshold_medium"));
+    let color_high = get_field(settings, &format!("{name}.color_high"));
+    let color_medium = get_field(settings, &format!("{name}.color_medium"));
+    let color_low = get_field(settings, &format!("{name}.color_low"));
+
+    let component = ClaudeStatusLineComponentConfig {
+        color,
+        icon,
+        disabled,
+        style,
+        threshold_high,
+        threshold_medium,
+        color_high,
+        color_medium,
+        color_low,
+    };
+
+    if component.is_empty() {
+        None
+    } else {
+        Some(component)
+    }
 }
 
 #[cfg(test)]
@@ -95,23 +356,32 @@ mod tests {
     #[test]
     fn get_color_or_returns_configured_value() {
         let config = ClaudeStatusLineComponentConfig {
-            color: Some(100),
-            icon: None,
+            color: Some(ColorSpec::Fixed(100)),
+            ..Default::default()
         };
-        assert_eq!(100, config.get_color_or(42));
+        assert_eq!(Color::Fixed(100), config.get_color_or(Color::Fixed(42)));
     }
 
     #[test]
     fn get_color_or_returns_default_when_unset() {
         let config = ClaudeStatusLineComponentConfig::default();
-        assert_eq!(42, config.get_color_or(42));
+        assert_eq!(Color::Fixed(42), config.get_color_or(Color::Fixed(42)));
+    }
+
+    #[test]
+    fn get_style_or_applies_attributes() {
+        let config = ClaudeStatusLineComponentConfig {
+            style: Some(vec![StyleAttr::Bold]),
+            ..Default::default()
+        };
+        assert!(config.get_style_or(Color::Fixed(42)).is_bold);
     }
 
     #[test]
     fn get_icon_or_returns_configured_value() {
         let config = ClaudeStatusLineComponentConfig {
-            color: None,
             icon: Some("+".to_string()),
+            ..Default::default()
         };
         assert_eq!("+", config.get_icon_or("🤖"));
     }
@@ -122,6 +392,45 @@ mod tests {
         assert_eq!("🤖", config.get_icon_or("🤖"));
     }
 
+    #[test]
+    fn components_defaults_to_fixed_order() {
+        let config = ClaudeStatusLineConfig::default();
+        assert_eq!(DEFAULT_ORDER.to_vec(), config.components());
+    }
+
+    #[test]
+    fn components_honors_custom_order() {
+        let config = ClaudeStatusLineConfig {
+            order: Some(vec!["cost".to_string(), "model".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(vec!["cost", "model"], config.components());
+    }
+
+    #[test]
+    fn components_drops_unknown_order_names() {
+        let config = ClaudeStatusLineConfig {
+            order: Some(vec!["model".to_string(), "bogus".to_string(), "cost".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(vec!["model", "cost"], config.components());
+    }
+
+    #[test]
+    fn components_drops_disabled() {
+        let config = ClaudeStatusLineConfig {
+            duration: Some(ClaudeStatusLineComponentConfig {
+                disabled: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            vec!["model", "percentage", "tokens", "cost"],
+            config.components(),
+        );
+    }
+
     #[test]
     fn load_from_env() {
         let _lock = ENV_MUTEX.lock().unwrap();
@@ -139,7 +448,35 @@ mod tests {
             }
         }
 
-        assert_eq!(Some(200), config.cost.unwrap().color);
+        assert_eq!(Some(ColorSpec::Fixed(200)), config.cost.unwrap().color);
+    }
+
+    #[test]
+    fn invalid_field_falls_back_without_panicking() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("statusline.toml");
+        // `color` is out of range for a u8, but the sibling `icon` is fine.
+        std::fs::write(&path, "[cost]\ncolor = 999\nicon = \"C\"\n").unwrap();
+
+        let _lock = ENV_MUTEX.lock().unwrap();
+        let config = load_config_from(path.to_str().unwrap());
+
+        let cost = config.cost.unwrap();
+        assert_eq!(None, cost.color);
+        assert_eq!(Some("C".to_string()), cost.icon);
+    }
+
+    #[test]
+    fn malformed_toml_yields_default_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("statusline.toml");
+        std::fs::write(&path, "this is not = valid = toml\n").unwrap();
+
+        let _lock = ENV_MUTEX.lock().unwrap();
+        let config = load_config_from(path.to_str().unwrap());
+
+        assert!(config.cost.is_none());
+        assert!(config.model.is_none());
     }
 
     #[test]
@@ -152,7 +489,33 @@ mod tests {
         let config = load_config_from(path.to_str().unwrap());
 
         let duration = config.duration.unwrap();
-        assert_eq!(Some(39), duration.color);
+        assert_eq!(Some(ColorSpec::Fixed(39)), duration.color);
         assert_eq!(Some("T".to_string()), duration.icon);
     }
+
+    #[test]
+    fn load_hex_and_style_from_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("statusline.toml");
+        std::fs::write(&path, "[model]\ncolor = \"#ff8800\"\nstyle = [\"bold\"]\n").unwrap();
+
+        let _lock = ENV_MUTEX.lock().unwrap();
+        let config = load_config_from(path.to_str().unwrap());
+
+        let model = config.model.unwrap();
+        assert_eq!(Some(ColorSpec::Rgb(0xff, 0x88, 0x00)), model.color);
+        assert!(model.get_style_or(Color::Fixed(1)).is_bold);
+    }
+
+    #[test]
+    fn threshold_and_threshold_colors_honor_config() {
+        let config = ClaudeStatusLineComponentConfig {
+            threshold_high: Some(90),
+            color_high: Some(ColorSpec::Named(Color::Red)),
+            ..Default::default()
+        };
+        assert_eq!(90, config.threshold_high_or(80));
+        assert_eq!(Color::Red, config.color_high_or(Color::Fixed(203)));
+        assert_eq!(70, config.threshold_medium_or(70));
+    }
 }
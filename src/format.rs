@@ -0,0 +1,179 @@
+//! Parsing for the optional `format` template.
+//!
+//! A template is a string of literal text interspersed with `{...}` placeholders.
+//! Each placeholder is one of:
+//!
+//! * `{component}` — render a known component (e.g. `{model}`).
+//! * `{?component ...}` — render the inner block only when the component has a
+//!   present/non-zero value.
+//! * `{!component ...}` — render the inner block only when it does not.
+//!
+//! Anything that isn't a recognized placeholder is emitted verbatim, so an
+//! unknown `{foo}` comes back out as the literal text `{foo}`.
+
+use crate::config::COMPONENTS;
+
+#[derive(Debug, PartialEq)]
+pub enum Token {
+    Literal(String),
+    Component(String),
+    Conditional {
+        component: String,
+        present: bool,
+        inner: Vec<Token>,
+    },
+}
+
+/// Parse `template` into a flat list of tokens, recursing into conditional blocks.
+pub fn parse(template: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let chars: Vec<char> = template.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '{' {
+            if let Some(end) = matching_brace(&chars, i) {
+                let inner: String = chars[i + 1..end].iter().collect();
+                match classify(&inner) {
+                    Some(token) => {
+                        if !literal.is_empty() {
+                            tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                        }
+                        tokens.push(token);
+                    }
+                    // Unknown placeholder: emit the braces and content literally.
+                    None => {
+                        literal.push('{');
+                        literal.push_str(&inner);
+                        literal.push('}');
+                    }
+                }
+                i = end + 1;
+                continue;
+            }
+        }
+
+        literal.push(chars[i]);
+        i += 1;
+    }
+
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(literal));
+    }
+
+    tokens
+}
+
+/// Turn the text between a matched pair of braces into a token, or `None` when
+/// it doesn't name a recognized placeholder.
+fn classify(inner: &str) -> Option<Token> {
+    if let Some(rest) = inner.strip_prefix('?') {
+        conditional(rest, true)
+    } else if let Some(rest) = inner.strip_prefix('!') {
+        conditional(rest, false)
+    } else if COMPONENTS.contains(&inner) {
+        Some(Token::Component(inner.to_string()))
+    } else {
+        None
+    }
+}
+
+fn conditional(rest: &str, present: bool) -> Option<Token> {
+    let (component, inner) = match rest.split_once(' ') {
+        Some((component, inner)) => (component, inner),
+        None => (rest, ""),
+    };
+
+    if !COMPONENTS.contains(&component) {
+        return None;
+    }
+
+    Some(Token::Conditional {
+        component: component.to_string(),
+        present,
+        inner: parse(inner),
+    })
+}
+
+/// Find the index of the `}` that closes the `{` at `open`, honoring nesting.
+fn matching_brace(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, c) in chars[open..].iter().enumerate() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + offset);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_literal_only() {
+        assert_eq!(vec![Token::Literal("hello".to_string())], parse("hello"));
+    }
+
+    #[test]
+    fn parses_component() {
+        assert_eq!(vec![Token::Component("model".to_string())], parse("{model}"));
+    }
+
+    #[test]
+    fn parses_literals_between_components() {
+        assert_eq!(
+            vec![
+                Token::Component("model".to_string()),
+                Token::Literal(" | ".to_string()),
+                Token::Component("cost".to_string()),
+            ],
+            parse("{model} | {cost}"),
+        );
+    }
+
+    #[test]
+    fn unknown_placeholder_becomes_literal() {
+        assert_eq!(vec![Token::Literal("{foo}".to_string())], parse("{foo}"));
+    }
+
+    #[test]
+    fn parses_present_conditional() {
+        assert_eq!(
+            vec![Token::Conditional {
+                component: "cost".to_string(),
+                present: true,
+                inner: vec![Token::Component("cost".to_string())],
+            }],
+            parse("{?cost {cost}}"),
+        );
+    }
+
+    #[test]
+    fn parses_absent_conditional_with_literal() {
+        assert_eq!(
+            vec![Token::Conditional {
+                component: "cost".to_string(),
+                present: false,
+                inner: vec![Token::Literal("no cost".to_string())],
+            }],
+            parse("{!cost no cost}"),
+        );
+    }
+
+    #[test]
+    fn conditional_on_unknown_component_becomes_literal() {
+        assert_eq!(
+            vec![Token::Literal("{?foo bar}".to_string())],
+            parse("{?foo bar}"),
+        );
+    }
+}
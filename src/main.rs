@@ -1,4 +1,7 @@
+mod color;
 mod config;
+mod format;
+mod session;
 
 use std::fmt;
 use std::io;
@@ -8,6 +11,8 @@ use millisecond::prelude::*;
 use serde::Deserialize;
 
 use config::get_config;
+use format::Token;
+use session::SessionStats;
 
 // Colors
 const BRIGHT_GREEN: u8 = 46;
@@ -24,6 +29,12 @@ const COST_ICON: &str = "💰";
 const DURATION_ICON: &str = "⏱️";
 const MODEL_ICON: &str = "🤖";
 const TOKENS_ICON: &str = "🪙";
+const SESSION_COST_ICON: &str = "🧾";
+const SESSION_TOKENS_ICON: &str = "🪙";
+const LATENCY_ICON: &str = "📶";
+
+// Separator placed between components in the default (non-template) layout.
+const SEPARATOR: &str = " | ";
 
 const CONTEXT_BAR_WIDTH: usize = 10;
 const CONTEXT_THRESHOLD_HIGH: i32 = 80; // Auto-compaction seems to kick in around 83%.
@@ -37,17 +48,88 @@ struct ClaudeStatusLineData {
     model: Model,
     percentage: Percentage,
     tokens: Tokens,
+    session_cost: SessionCost,
+    session_tokens: SessionTokens,
+    latency_mean: LatencyMean,
+    latency_p95: LatencyP95,
 }
 
 impl fmt::Display for ClaudeStatusLineData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let cost = self.cost;
-        let duration = self.duration;
-        let model = &self.model;
-        let percentage = self.percentage;
-        let tokens = self.tokens;
+        let config = get_config();
+        match config.format.as_deref() {
+            Some(template) => self.write_tokens(f, &format::parse(template)),
+            None => {
+                for (i, name) in config.components().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(SEPARATOR)?;
+                    }
+                    self.write_component(f, name)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ClaudeStatusLineData {
+    fn write_tokens(&self, f: &mut fmt::Formatter<'_>, tokens: &[Token]) -> fmt::Result {
+        for token in tokens {
+            match token {
+                Token::Literal(text) => f.write_str(text)?,
+                Token::Component(name) => self.write_component(f, name)?,
+                Token::Conditional {
+                    component,
+                    present,
+                    inner,
+                } => {
+                    if self.is_present(component) == *present {
+                        self.write_tokens(f, inner)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-        write!(f, "{model} | {percentage} | {tokens} | {cost} | {duration}")
+    fn write_component(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        match name {
+            "model" => write!(f, "{}", self.model),
+            "percentage" => write!(f, "{}", self.percentage),
+            "tokens" => write!(f, "{}", self.tokens),
+            "cost" => write!(f, "{}", self.cost),
+            "duration" => write!(f, "{}", self.duration),
+            "session_cost" => write!(f, "{}", self.session_cost),
+            "session_tokens" => write!(f, "{}", self.session_tokens),
+            "latency_mean" => write!(f, "{}", self.latency_mean),
+            "latency_p95" => write!(f, "{}", self.latency_p95),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a component currently holds a present/non-zero value, used to
+    /// resolve `{?component ...}` and `{!component ...}` segments.
+    fn is_present(&self, name: &str) -> bool {
+        match name {
+            "model" => !self.model.display_name.is_empty(),
+            "percentage" => self.percentage.used_percentage.unwrap_or_default() > 0.0,
+            "tokens" => {
+                self.tokens.total_input_tokens.unwrap_or_default() > 0
+                    || self.tokens.total_output_tokens.unwrap_or_default() > 0
+            }
+            "cost" => self.cost.total_cost_usd.unwrap_or_default() > 0.0,
+            "duration" => self.duration.total_api_duration_ms.unwrap_or_default() > 0,
+            "session_cost" => self.session_cost.total_cost_usd > 0.0,
+            "session_tokens" => {
+                self.session_tokens.total_input_tokens > 0
+                    || self.session_tokens.total_output_tokens > 0
+            }
+            "latency_mean" => self.latency_mean.duration_ms > 0,
+            "latency_p95" => self.latency_p95.duration_ms > 0,
+            _ => false,
+        }
     }
 }
 
@@ -55,12 +137,25 @@ impl From<RawClaudeStatusLineData> for ClaudeStatusLineData {
     fn from(raw: RawClaudeStatusLineData) -> Self {
         let context = raw.context_window.unwrap_or_default();
         let cost = raw.cost.unwrap_or_default();
+
+        let stats = session::record(
+            raw.session_id.as_deref(),
+            cost.amount.total_cost_usd.unwrap_or_default(),
+            context.tokens.total_input_tokens.unwrap_or_default(),
+            context.tokens.total_output_tokens.unwrap_or_default(),
+            cost.duration.total_api_duration_ms.unwrap_or_default(),
+        );
+
         Self {
             cost: cost.amount,
             duration: cost.duration,
             model: raw.model,
             percentage: context.percentage,
             tokens: context.tokens,
+            session_cost: SessionCost::from(stats),
+            session_tokens: SessionTokens::from(stats),
+            latency_mean: LatencyMean::from(stats),
+            latency_p95: LatencyP95::from(stats),
         }
     }
 }
@@ -70,6 +165,7 @@ struct RawClaudeStatusLineData {
     cost: Option<Cost>,
     context_window: Option<ContextWindow>,
     model: Model,
+    session_id: Option<String>,
 }
 
 #[derive(Deserialize, Default)]
@@ -96,11 +192,11 @@ struct Amount {
 impl fmt::Display for Amount {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let config = get_config().cost.clone().unwrap_or_default();
-        let color = config.get_color_or(LAVENDAR);
+        let style = config.get_style_or(Color::Fixed(LAVENDAR));
         let icon = config.get_icon_or(COST_ICON);
 
         let cost = self.total_cost_usd.unwrap_or_default();
-        let cost = Color::Fixed(color).paint(format!("${cost:.2}"));
+        let cost = style.paint(format!("${cost:.2}"));
 
         write!(f, "{icon} {cost}")
     }
@@ -114,29 +210,32 @@ struct Duration {
 impl fmt::Display for Duration {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let config = get_config().duration.clone().unwrap_or_default();
-        let color = config.get_color_or(DODGER_BLUE);
+        let style = config.get_style_or(Color::Fixed(DODGER_BLUE));
         let icon = config.get_icon_or(DURATION_ICON);
 
-        let duration = Color::Fixed(color).paint(match self.total_api_duration_ms {
-            Some(0) | None => "0s".to_string(),
-            _ => {
-                let ms = Millisecond::from(core::time::Duration::from_millis(
-                    self.total_api_duration_ms.unwrap_or_default(),
-                ));
-                ms.pretty_with(MillisecondOption {
-                    seconds: SecondsOptions::CombineWith {
-                        precision: Some(0),
-                        fixed_width: false,
-                    },
-                    ..Default::default()
-                })
-            }
-        });
+        let duration = style.paint(pretty_duration(self.total_api_duration_ms.unwrap_or_default()));
 
         write!(f, "{icon} {duration}")
     }
 }
 
+/// Render a millisecond count the way the statusline shows durations, e.g.
+/// `0s`, `1s`, `1m`.
+fn pretty_duration(ms: u64) -> String {
+    if ms == 0 {
+        return "0s".to_string();
+    }
+
+    let ms = Millisecond::from(core::time::Duration::from_millis(ms));
+    ms.pretty_with(MillisecondOption {
+        seconds: SecondsOptions::CombineWith {
+            precision: Some(0),
+            fixed_width: false,
+        },
+        ..Default::default()
+    })
+}
+
 #[derive(Deserialize)]
 struct Model {
     display_name: String,
@@ -145,10 +244,10 @@ struct Model {
 impl fmt::Display for Model {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let config = get_config().model.clone().unwrap_or_default();
-        let color = config.get_color_or(ORANGE);
+        let style = config.get_style_or(Color::Fixed(ORANGE));
         let icon = config.get_icon_or(MODEL_ICON);
 
-        let display_name = Color::Fixed(color).paint(&self.display_name);
+        let display_name = style.paint(&self.display_name);
 
         write!(f, "{icon} {display_name}")
     }
@@ -167,7 +266,7 @@ impl fmt::Display for Percentage {
         let percent = self.used_percentage.unwrap_or_default() as i32;
         let filled = percent * CONTEXT_BAR_WIDTH as i32 / 100;
         let bar = "▓".repeat(filled as usize) + &"░".repeat(CONTEXT_BAR_WIDTH - filled as usize);
-        let context = self.color().paint(format!("{bar} {percent}%"));
+        let context = config.style_with(self.color()).paint(format!("{bar} {percent}%"));
 
         write!(f, "{icon} {context}")
     }
@@ -175,13 +274,14 @@ impl fmt::Display for Percentage {
 
 impl Percentage {
     fn color(&self) -> Color {
+        let config = get_config().percentage.clone().unwrap_or_default();
         let percent = self.used_percentage.unwrap_or_default() as i32;
-        if percent > CONTEXT_THRESHOLD_HIGH {
-            Color::Fixed(PINK_RED)
-        } else if percent > CONTEXT_THRESHOLD_MEDIUM {
-            Color::Fixed(BRIGHT_YELLOW)
+        if percent > config.threshold_high_or(CONTEXT_THRESHOLD_HIGH) {
+            config.color_high_or(Color::Fixed(PINK_RED))
+        } else if percent > config.threshold_medium_or(CONTEXT_THRESHOLD_MEDIUM) {
+            config.color_medium_or(Color::Fixed(BRIGHT_YELLOW))
         } else {
-            Color::Fixed(BRIGHT_GREEN)
+            config.color_low_or(Color::Fixed(BRIGHT_GREEN))
         }
     }
 }
@@ -195,17 +295,121 @@ struct Tokens {
 impl fmt::Display for Tokens {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let config = get_config().tokens.clone().unwrap_or_default();
-        let color = config.get_color_or(MAGENTA_PINK);
+        let style = config.get_style_or(Color::Fixed(MAGENTA_PINK));
         let icon = config.get_icon_or(TOKENS_ICON);
 
         let input = self.total_input_tokens.unwrap_or_default();
         let output = self.total_output_tokens.unwrap_or_default();
-        let tokens = Color::Fixed(color).paint(format!("{input}↑ {output}↓"));
+        let tokens = style.paint(format!("{input}↑ {output}↓"));
+
+        write!(f, "{icon} {tokens}")
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct SessionCost {
+    total_cost_usd: f64,
+}
+
+impl From<Option<SessionStats>> for SessionCost {
+    fn from(stats: Option<SessionStats>) -> Self {
+        Self {
+            total_cost_usd: stats.map(|s| s.total_cost_usd).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for SessionCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = get_config().session_cost.clone().unwrap_or_default();
+        let style = config.get_style_or(Color::Fixed(LAVENDAR));
+        let icon = config.get_icon_or(SESSION_COST_ICON);
+
+        let cost = style.paint(format!("${:.2}", self.total_cost_usd));
+
+        write!(f, "{icon} {cost}")
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct SessionTokens {
+    total_input_tokens: u64,
+    total_output_tokens: u64,
+}
+
+impl From<Option<SessionStats>> for SessionTokens {
+    fn from(stats: Option<SessionStats>) -> Self {
+        Self {
+            total_input_tokens: stats.map(|s| s.total_input_tokens).unwrap_or_default(),
+            total_output_tokens: stats.map(|s| s.total_output_tokens).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for SessionTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = get_config().session_tokens.clone().unwrap_or_default();
+        let style = config.get_style_or(Color::Fixed(MAGENTA_PINK));
+        let icon = config.get_icon_or(SESSION_TOKENS_ICON);
+
+        let input = self.total_input_tokens;
+        let output = self.total_output_tokens;
+        let tokens = style.paint(format!("{input}↑ {output}↓"));
 
         write!(f, "{icon} {tokens}")
     }
 }
 
+#[derive(Default, Clone, Copy)]
+struct LatencyMean {
+    duration_ms: u64,
+}
+
+impl From<Option<SessionStats>> for LatencyMean {
+    fn from(stats: Option<SessionStats>) -> Self {
+        Self {
+            duration_ms: stats.map(|s| s.mean_duration_ms.round() as u64).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for LatencyMean {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = get_config().latency_mean.clone().unwrap_or_default();
+        let style = config.get_style_or(Color::Fixed(DODGER_BLUE));
+        let icon = config.get_icon_or(LATENCY_ICON);
+
+        let latency = style.paint(format!("x̄ {}", pretty_duration(self.duration_ms)));
+
+        write!(f, "{icon} {latency}")
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct LatencyP95 {
+    duration_ms: u64,
+}
+
+impl From<Option<SessionStats>> for LatencyP95 {
+    fn from(stats: Option<SessionStats>) -> Self {
+        Self {
+            duration_ms: stats.map(|s| s.p95_duration_ms).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for LatencyP95 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = get_config().latency_p95.clone().unwrap_or_default();
+        let style = config.get_style_or(Color::Fixed(DODGER_BLUE));
+        let icon = config.get_icon_or(LATENCY_ICON);
+
+        let latency = style.paint(format!("p95 {}", pretty_duration(self.duration_ms)));
+
+        write!(f, "{icon} {latency}")
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let data: ClaudeStatusLineData = serde_json::from_reader(io::stdin())?;
     println!("{data}");
@@ -238,6 +442,10 @@ mod tests {
             },
             percentage: Percentage::default(),
             tokens: Tokens::default(),
+            session_cost: SessionCost::default(),
+            session_tokens: SessionTokens::default(),
+            latency_mean: LatencyMean::default(),
+            latency_p95: LatencyP95::default(),
         };
 
         let output = format!("{data}");
@@ -294,6 +502,10 @@ mod tests {
                 total_input_tokens: Some(5),
                 total_output_tokens: Some(10),
             },
+            session_cost: SessionCost::default(),
+            session_tokens: SessionTokens::default(),
+            latency_mean: LatencyMean::default(),
+            latency_p95: LatencyP95::default(),
         };
 
         let output = format!("{data}");
@@ -0,0 +1,321 @@
+//! Cross-invocation session tracking.
+//!
+//! Claude Code re-runs the statusline constantly, each time handing over only
+//! the current snapshot. When enabled in `statusline.toml`, this module records
+//! every invocation's cost, token counts, and API duration in a small JSONL
+//! file under `~/.claude/`, keyed by session id, and derives session-wide stats
+//! (running cost, cumulative tokens, mean and p95 latency) for display. The
+//! file is not append-only: each call reads it, adds the new entry, caps the
+//! history, and rewrites the whole file.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_config;
+
+/// One invocation's snapshot, as persisted to the history file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Entry {
+    session_id: Option<String>,
+    total_cost_usd: f64,
+    total_input_tokens: u64,
+    total_output_tokens: u64,
+    total_api_duration_ms: u64,
+}
+
+/// Session-wide stats derived from the history file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionStats {
+    pub total_cost_usd: f64,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub mean_duration_ms: f64,
+    pub p95_duration_ms: u64,
+}
+
+/// Record the current invocation and return the derived stats for its session.
+///
+/// Returns `None` when session tracking is disabled or the history file can't
+/// be written — the statusline should still render without it. A missing or
+/// unreadable history file is treated as empty history.
+pub fn record(
+    session_id: Option<&str>,
+    total_cost_usd: f64,
+    total_input_tokens: u64,
+    total_output_tokens: u64,
+    total_api_duration_ms: u64,
+) -> Option<SessionStats> {
+    let config = get_config().session.clone().unwrap_or_default();
+    if !config.is_enabled() {
+        return None;
+    }
+
+    let path = config.path();
+    let entry = Entry {
+        session_id: session_id.map(str::to_string),
+        total_cost_usd,
+        total_input_tokens,
+        total_output_tokens,
+        total_api_duration_ms,
+    };
+
+    let mut history = read_history(&path);
+    history.push(entry);
+
+    // Keep only the most recent entries *per session*, then evict whole stale
+    // sessions beyond the global cap, so the file — and the work done each call
+    // — stays bounded without one busy session evicting another's history.
+    cap_per_session(&mut history, config.history_limit());
+    evict_stale_sessions(&mut history, config.max_sessions());
+
+    if let Err(err) = write_history(&path, &history) {
+        eprintln!("claude-statusline: could not persist session history: {err}");
+        return None;
+    }
+
+    let current: Vec<Entry> = history
+        .into_iter()
+        .filter(|e| e.session_id.as_deref() == session_id)
+        .collect();
+
+    Some(compute_stats(&current))
+}
+
+/// Drop the oldest entries of any session that exceeds `limit`, keeping each
+/// session's most recent `limit` entries while preserving overall order.
+fn cap_per_session(history: &mut Vec<Entry>, limit: usize) {
+    use std::collections::HashMap;
+
+    let mut counts: HashMap<Option<String>, usize> = HashMap::new();
+    let mut kept = Vec::with_capacity(history.len());
+    for entry in history.drain(..).rev() {
+        let count = counts.entry(entry.session_id.clone()).or_insert(0);
+        if *count < limit {
+            *count += 1;
+            kept.push(entry);
+        }
+    }
+    kept.reverse();
+    *history = kept;
+}
+
+/// Drop entries belonging to the least-recently-active sessions once more than
+/// `max_sessions` distinct sessions are present, keeping the most recent ones.
+/// This bounds total file size as new sessions accumulate over time.
+fn evict_stale_sessions(history: &mut Vec<Entry>, max_sessions: usize) {
+    use std::collections::HashSet;
+
+    // Sessions in order of most-recent activity (last appearance), newest first.
+    let mut recent = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in history.iter().rev() {
+        if seen.insert(entry.session_id.clone()) {
+            recent.push(entry.session_id.clone());
+        }
+    }
+
+    if recent.len() <= max_sessions {
+        return;
+    }
+
+    let keep: HashSet<Option<String>> = recent.into_iter().take(max_sessions).collect();
+    history.retain(|entry| keep.contains(&entry.session_id));
+}
+
+fn read_history(path: &str) -> Vec<Entry> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn write_history(path: &str, history: &[Entry]) -> std::io::Result<()> {
+    let mut out = String::new();
+    for entry in history {
+        out.push_str(&serde_json::to_string(entry).unwrap_or_default());
+        out.push('\n');
+    }
+    fs::write(path, out)
+}
+
+/// Aggregate a session's entries into [`SessionStats`].
+///
+/// Each entry is a snapshot of the session so far. `total_cost_usd` is a
+/// monotonic cumulative figure, so the running cost is simply the latest
+/// snapshot. Token counts, by contrast, report current context-window
+/// occupancy (which drops after auto-compaction), so cumulative session tokens
+/// seed from the first retained snapshot's occupancy and then add the positive
+/// per-invocation growths on top. Per-call latencies are the positive
+/// differences between consecutive cumulative `total_api_duration_ms` values;
+/// unlike the tokens, their sampling starts from the *second* retained entry,
+/// since the first entry's cumulative baseline — larger still once early
+/// entries are capped away — is meaningless as a per-call figure.
+fn compute_stats(entries: &[Entry]) -> SessionStats {
+    let Some(latest) = entries.last() else {
+        return SessionStats::default();
+    };
+    let first = &entries[0];
+
+    let mut latencies = Vec::new();
+    // Seed from the first snapshot's occupancy so a brand-new session's initial
+    // context is counted, then accumulate each later invocation's growth.
+    let mut total_input_tokens = first.total_input_tokens;
+    let mut total_output_tokens = first.total_output_tokens;
+    let mut previous: Option<&Entry> = None;
+    for entry in entries {
+        if let Some(previous) = previous {
+            let delta = entry.total_api_duration_ms.saturating_sub(previous.total_api_duration_ms);
+            if delta > 0 {
+                latencies.push(delta);
+            }
+            total_input_tokens +=
+                entry.total_input_tokens.saturating_sub(previous.total_input_tokens);
+            total_output_tokens +=
+                entry.total_output_tokens.saturating_sub(previous.total_output_tokens);
+        }
+        previous = Some(entry);
+    }
+
+    let (mean_duration_ms, p95_duration_ms) = if latencies.is_empty() {
+        (0.0, 0)
+    } else {
+        let mean = latencies.iter().sum::<u64>() as f64 / latencies.len() as f64;
+        latencies.sort_unstable();
+        // Nearest-rank percentile: the value at ceil(0.95 * n) - 1 once sorted.
+        let index = ((0.95 * latencies.len() as f64).ceil() as usize)
+            .saturating_sub(1)
+            .min(latencies.len() - 1);
+        (mean, latencies[index])
+    };
+
+    SessionStats {
+        total_cost_usd: latest.total_cost_usd,
+        total_input_tokens,
+        total_output_tokens,
+        mean_duration_ms,
+        p95_duration_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(session_id: &str, cost: f64, input: u64, output: u64, duration: u64) -> Entry {
+        Entry {
+            session_id: Some(session_id.to_string()),
+            total_cost_usd: cost,
+            total_input_tokens: input,
+            total_output_tokens: output,
+            total_api_duration_ms: duration,
+        }
+    }
+
+    #[test]
+    fn empty_history_is_default() {
+        assert_eq!(SessionStats::default(), compute_stats(&[]));
+    }
+
+    #[test]
+    fn cost_tracks_latest_tokens_accumulate_deltas() {
+        let entries = vec![entry("s", 1.0, 10, 5, 100), entry("s", 2.0, 20, 7, 400)];
+        let stats = compute_stats(&entries);
+        // Cost is a monotonic cumulative figure: the latest snapshot is the total.
+        assert_eq!(2.0, stats.total_cost_usd);
+        // Tokens report context-window occupancy: seed from the first snapshot
+        // (10↑/5↓) plus later growth (10↑/2↓) = 20↑/7↓.
+        assert_eq!(20, stats.total_input_tokens);
+        assert_eq!(7, stats.total_output_tokens);
+        // Sampling starts from the second entry, so the only per-call latency
+        // is the delta 300.
+        assert_eq!(300.0, stats.mean_duration_ms);
+    }
+
+    #[test]
+    fn p95_uses_nearest_rank_over_deltas() {
+        // Cumulative durations 100, 300, …, 5500. The first entry is only a
+        // baseline, so the per-call deltas are 200, 300, …, 1000.
+        let mut cumulative = 0;
+        let entries: Vec<Entry> = (1..=10)
+            .map(|i| {
+                cumulative += i * 100;
+                entry("s", 0.0, 0, 0, cumulative)
+            })
+            .collect();
+        // n = 9 → ceil(0.95 * 9) - 1 = 8 → the largest delta.
+        assert_eq!(1000, compute_stats(&entries).p95_duration_ms);
+    }
+
+    #[test]
+    fn first_invocation_counts_initial_token_occupancy() {
+        // A brand-new session's single snapshot should report its context, not 0.
+        let stats = compute_stats(&[entry("s", 0.5, 1234, 56, 300)]);
+        assert_eq!(1234, stats.total_input_tokens);
+        assert_eq!(56, stats.total_output_tokens);
+    }
+
+    #[test]
+    fn single_entry_yields_no_latency_sample() {
+        // With only one snapshot there is no prior baseline to diff against, so
+        // its cumulative duration must not be mistaken for a per-call latency.
+        let stats = compute_stats(&[entry("s", 0.0, 0, 0, 500)]);
+        assert_eq!(0.0, stats.mean_duration_ms);
+        assert_eq!(0, stats.p95_duration_ms);
+    }
+
+    #[test]
+    fn capped_session_drops_baseline_sample() {
+        // After early entries are capped away, the first retained entry carries
+        // a large cumulative baseline. It must be skipped, not counted as one
+        // giant per-call sample that inflates the mean/p95.
+        let entries = vec![
+            entry("s", 0.0, 0, 0, 10_000),
+            entry("s", 0.0, 0, 0, 10_100),
+            entry("s", 0.0, 0, 0, 10_200),
+        ];
+        let stats = compute_stats(&entries);
+        assert_eq!(100.0, stats.mean_duration_ms);
+        assert_eq!(100, stats.p95_duration_ms);
+    }
+
+    #[test]
+    fn cap_is_per_session() {
+        let mut history = vec![
+            entry("a", 0.0, 0, 0, 100),
+            entry("b", 0.0, 0, 0, 100),
+            entry("a", 0.0, 0, 0, 200),
+            entry("b", 0.0, 0, 0, 200),
+        ];
+        cap_per_session(&mut history, 1);
+
+        // One entry survives per session — neither evicts the other.
+        assert_eq!(2, history.len());
+        assert_eq!(Some("a".to_string()), history[0].session_id);
+        assert_eq!(200, history[0].total_api_duration_ms);
+        assert_eq!(Some("b".to_string()), history[1].session_id);
+        assert_eq!(200, history[1].total_api_duration_ms);
+    }
+
+    #[test]
+    fn total_history_stays_bounded_across_many_sessions() {
+        // Simulate a user accumulating ever more distinct sessions, one entry
+        // each. The total must not grow past the global session cap.
+        let mut history = Vec::new();
+        for i in 0..1000 {
+            history.push(entry(&format!("s{i}"), 0.0, 0, 0, 100));
+            cap_per_session(&mut history, 1000);
+            evict_stale_sessions(&mut history, 3);
+            assert!(history.len() <= 3);
+        }
+
+        // Only the three most recent sessions survive.
+        assert_eq!(3, history.len());
+        assert_eq!(Some("s997".to_string()), history[0].session_id);
+        assert_eq!(Some("s999".to_string()), history[2].session_id);
+    }
+}